@@ -65,6 +65,7 @@ impl BinaryTree {
   }
 
   pub fn find_first_free(&self) -> Option<usize> { self.find_leaf(true, false)}
+  pub fn find_first_full(&self) -> Option<usize> { self.find_leaf(true, true)}
   pub fn find_last_full(&self) -> Option<usize> { self.find_leaf(false, true)}
 
   pub fn set_leaf(&mut self, idx: usize, full: bool) -> Option<()> {
@@ -90,9 +91,29 @@ impl BinaryTree {
     Some(self.tree[idx & !1][idx & 1][1])
   }
 
+  pub fn size(&self) -> usize { self.size }
+
+  /// Checks the paths agree with the leaves by comparing both searches against a linear scan.
+  pub fn validate(&self) -> Result<(), String> {
+    let expected_len = if self.size == 0 { 0 } else { self.size.next_power_of_two().max(2) - 1 };
+    if self.tree.len() != expected_len {
+      return Err(format!("tree holds {} nodes but {} leaves need {expected_len}", self.tree.len(), self.size))
+    }
+    let first_free = (0 .. self.size).find(|idx| !self.is_full(*idx).unwrap());
+    if self.find_first_free() != first_free {
+      return Err(format!("first free leaf is {first_free:?} but the tree finds {:?}", self.find_first_free()))
+    }
+    let last_full = (0 .. self.size).rev().find(|idx| self.is_full(*idx).unwrap());
+    if self.find_last_full() != last_full {
+      return Err(format!("last full leaf is {last_full:?} but the tree finds {:?}", self.find_last_full()))
+    }
+    Ok(())
+  }
+
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn write() {
   let mut tree = BinaryTree::new();
   tree.resize(7);
@@ -127,6 +148,18 @@ fn paths() {
 }
 
 #[test]
+fn find_first_full() {
+  let mut tree = BinaryTree::new();
+  assert_eq!(tree.find_first_full(), None);
+  tree.resize(8);
+  assert_eq!(tree.find_first_full(), None);
+  tree.set_leaf(5, true);
+  tree.set_leaf(3, true);
+  assert_eq!(tree.find_first_full().unwrap(), 3);
+}
+
+#[test]
+#[allow(clippy::bool_assert_comparison)]
 fn resize() {
   let mut tree = BinaryTree::new();
   tree.resize(8);
@@ -145,3 +178,15 @@ fn resize() {
 
 }
 
+#[test]
+fn validate() {
+  let mut tree = BinaryTree::new();
+  assert!(tree.validate().is_ok());
+  tree.resize(8);
+  tree.set_leaf(5, true);
+  assert!(tree.validate().is_ok());
+
+  // Flip a leaf without rebuilding its path
+  tree.tree[6][1] = [false, true];
+  assert!(tree.validate().unwrap_err().contains("last full leaf"));
+}
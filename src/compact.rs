@@ -0,0 +1,78 @@
+//! A serde format for [Pond] which skips free slots.
+//!
+//! The default [Pond] format writes every slot, free or not, which bloats sparse ponds.
+//! This one writes the pond's length plus `(index, data)` pairs for the occupied slots only,
+//! and rebuilds the same layout on the way back in, with every occupied slot at its old index.
+//! Free slots past the last occupied one aren't rebuilt. Use it on a field with
+//! `#[serde(with = "lilypads::compact")]`.
+//!
+//! Keeping indices means one slot at a huge index costs as much memory as a pond that long,
+//! so [deserialize] rejects anything needing more than [MAX_LEN] slots before allocating.
+//! For another limit, call [deserialize_bounded] from your own `deserialize_with` function.
+//!
+//! ```
+//! use lilypads::Pond;
+//! use serde::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Graph {
+//!   #[serde(with = "lilypads::compact")]
+//!   nodes: Pond<u32>,
+//!   #[serde(serialize_with = "lilypads::compact::serialize", deserialize_with = "small")]
+//!   edges: Pond<(u32, u32)>,
+//! }
+//!
+//! fn small<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Pond<(u32, u32)>, D::Error> {
+//!   lilypads::compact::deserialize_bounded(deserializer, 1024)
+//! }
+//! ```
+use crate::Pond;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+#[derive(Serialize)]
+struct CompactRef<'a, T> {
+  len: usize,
+  slots: Vec<(usize, &'a T)>,
+}
+
+#[derive(Deserialize)]
+struct Compact<T> {
+  len: usize,
+  slots: Vec<(usize, T)>,
+}
+
+/// Serializes `pond` as its length plus the occupied `(index, data)` pairs in ascending order.
+pub fn serialize<T: Serialize, S: Serializer>(pond: &Pond<T>, serializer: S) -> Result<S::Ok, S::Error> {
+  let mut slots = Vec::with_capacity(pond.occupied_count());
+  for idx in 0 .. pond.len() {
+    if let Some(data) = pond.get(idx) { slots.push((idx, data)) }
+  }
+  CompactRef { len: pond.len(), slots }.serialize(serializer)
+}
+
+/// The most slots [deserialize] will rebuild.
+pub const MAX_LEN: usize = 1 << 24;
+
+/// Rebuilds a [Pond] written by [serialize] with at most [MAX_LEN] slots, see [deserialize_bounded].
+pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Pond<T>, D::Error> {
+  deserialize_bounded(deserializer, MAX_LEN)
+}
+
+/// Rebuilds a [Pond] written by [serialize], erroring on slots outside the length or listed twice,
+/// or if the pond would need more than `max_len` slots. Nothing is allocated for the pond until
+/// its size has been checked.
+pub fn deserialize_bounded<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D, max_len: usize) -> Result<Pond<T>, D::Error> {
+  let compact = Compact::<T>::deserialize(deserializer)?;
+  let mut pond = Pond::new();
+  // The length is only trusted as a bound, the pond is sized by the slots actually listed
+  if let Some(&(idx, _)) = compact.slots.iter().find(|(idx, _)| *idx >= compact.len) {
+    return Err(D::Error::custom(format!("slot {idx} is outside of the pond's length {}", compact.len)))
+  }
+  let len = compact.slots.iter().map(|(idx, _)| idx + 1).max().unwrap_or(0);
+  if len > max_len { return Err(D::Error::custom(format!("{len} slots exceeds the limit of {max_len}"))) }
+  pond.resize(len);
+  for (idx, data) in compact.slots {
+    if pond.write(idx, data).is_some() { return Err(D::Error::custom(format!("slot {idx} is listed twice"))) }
+  }
+  Ok(pond)
+}
@@ -10,7 +10,7 @@
 //! contiguous as possible, [Pond::alloc] reserves the first (sequentially) free node and [Pond::defrag] +
 //! [Pond::trim] are provided to maintain contiguity on otherwise sparse allocations.
 //!
-//! This crate isn't yet thread safe, but that's eventually on the todo list probably.
+//! [Pond] itself isn't thread safe, but [SyncPond] wraps one in an `RwLock` so it can be shared between threads.
 //!
 //! # Example
 //! ```
@@ -49,39 +49,187 @@
 //! ```
 
 mod binary_tree;
+pub mod compact;
+#[cfg(feature = "rayon")]
+mod rayon;
+#[cfg(feature = "slab")]
+mod slab;
+mod sync;
 use binary_tree::BinaryTree;
-use std::collections::HashMap;
+pub use sync::{SyncPond, PondRef};
+use std::collections::{HashMap, HashSet};
 use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Hands every pond a unique id, so a [Checkpoint] can tell which pond it was taken from.
+fn next_pond_id() -> u64 {
+  static NEXT: AtomicU64 = AtomicU64::new(0);
+  NEXT.fetch_add(1, Ordering::Relaxed)
+}
 
 /// The struct used to pool T.
 ///
 /// The first available node will be allocated when you call [Pond::alloc],
 /// intending to keep the data as contiguous as possible. If you need total contiguity,
 /// [Pond::defrag] and [Pond::trim] should help with that.
-#[derive(Debug)]
+///
+/// A `Pond<T>` is [Send] whenever `T` is, and [Sync] whenever `T` is.
 pub struct Pond<T> {
   data : Vec< MaybeUninit<T> >,
   list: BinaryTree,
+  // Bumped whenever a slot's occupant leaves. Never shrinks, so handles to trimmed slots stay stale.
+  generations: Vec<u32>,
+  max_len: Option<usize>,
+  occupied: usize,
+  warn_on_leak: bool,
+  id: u64,
+}
+
+/// An index paired with the generation of the slot it was handed out for.
+///
+/// Slots are reused once freed, so a raw index can silently start pointing at unrelated data.
+/// A handle goes stale as soon as its occupant is freed, overwritten or moved (by [Pond::defrag] and friends),
+/// and every `_handle` method returns None for a stale handle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+  index: usize,
+  generation: u32,
+}
+impl Handle {
+  /// Returns the raw index this handle points at.
+  pub fn index(&self) -> usize { self.index }
+}
+
+// Pond should never be less thread-friendly than what it holds.
+const _: fn() = || {
+  fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<Pond<u32>>();
+  assert_send_sync::<SyncPond<u32>>();
+};
+
+/// Occupancy metrics returned by [Pond::stats].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryStats {
+  /// Slots currently holding data.
+  pub occupied: usize,
+  /// Free slots within [Pond::len].
+  pub free: usize,
+  /// Slots held, both free and full, as returned by [Pond::len].
+  pub len: usize,
+  /// Free slots sitting before the last occupied one, which [Pond::defrag] would fill.
+  pub interior_free: usize,
+  /// Slots the backing vec has room for without reallocating.
+  pub capacity: usize,
+  /// Bytes reserved for the slots themselves, `capacity * size_of::<T>()`.
+  pub bytes_for_data: usize,
+  /// Free slots over all slots, `free / len`, the same as [Pond::fragmentation].
+  pub fragmentation: f64,
+}
+impl MemoryStats {
+  /// Returns the fraction of capacity lost to interior free slots, `interior_free / capacity`, or 0.0 when there's no capacity.
+  /// Unlike [MemoryStats::fragmentation], this ignores free slots [Pond::trim] would drop.
+  pub fn fragmentation_ratio(&self) -> f64 {
+    if self.capacity == 0 { return 0.0 }
+    self.interior_free as f64 / self.capacity as f64
+  }
 }
+
+/// A snapshot of a [Pond], taken by [Pond::checkpoint] and rolled back to with [Pond::restore].
+///
+/// This currently holds a full copy of the pond, along with which pond it was taken from.
+pub struct Checkpoint<T> {
+  pond: Pond<T>,
+  origin: u64,
+}
+
+/// An iterator freeing a [Pond]'s slots as it goes, returned by [Pond::drain].
+pub struct Drain<'a, T> {
+  pond: &'a mut Pond<T>,
+}
+impl<T> Iterator for Drain<'_, T> {
+  type Item = (usize, T);
+  fn next(&mut self) -> Option<Self::Item> {
+    let idx = self.pond.list.find_first_full()?;
+    Some( (idx, self.pond.free(idx).unwrap()) )
+  }
+}
+impl<T> Drop for Drain<'_, T> {
+  fn drop(&mut self) {
+    self.for_each(drop);
+    self.pond.resize(0);
+  }
+}
+
+/// A view into a single slot of a [Pond], returned by [Pond::entry].
+pub enum Entry<'a, T> {
+  /// The slot holds data.
+  Occupied(&'a mut T),
+  /// The slot is free.
+  Vacant(VacantEntry<'a, T>),
+}
+impl<'a, T> Entry<'a, T> {
+  /// Returns the slot's data, writing `default` into it first if it's free.
+  pub fn or_insert(self, default: T) -> &'a mut T { self.or_insert_with(|| default) }
+
+  /// Returns the slot's data, writing the result of `f` into it first if it's free.
+  pub fn or_insert_with<F: FnOnce() -> T>(self, f: F) -> &'a mut T {
+    match self {
+      Entry::Occupied(data) => data,
+      Entry::Vacant(entry) => entry.insert(f()),
+    }
+  }
+
+  /// Runs `f` on the slot's data if it's occupied, passing the entry on either way.
+  pub fn and_modify<F: FnOnce(&mut T)>(self, f: F) -> Self {
+    match self {
+      Entry::Occupied(data) => { f(data); Entry::Occupied(data) }
+      vacant => vacant,
+    }
+  }
+}
+
+/// A free slot of a [Pond], see [Entry].
+pub struct VacantEntry<'a, T> {
+  pond: &'a mut Pond<T>,
+  idx: usize,
+}
+impl<'a, T> VacantEntry<'a, T> {
+  /// Returns the slot's index.
+  pub fn index(&self) -> usize { self.idx }
+
+  /// Writes `data` into the slot, returning a reference to it.
+  pub fn insert(self, data: T) -> &'a mut T {
+    self.pond.write(self.idx, data);
+    unsafe { self.pond.data[self.idx].assume_init_mut() }
+  }
+}
+
 impl<T> Pond<T> {
 
   fn is_reserved(&self, idx: usize) -> bool {
-    match self.list.is_full(idx) {
-      Some(result) => result,
-      None => false
-    }
+    self.list.is_full(idx).unwrap_or_default()
   }
 
   fn first_free(&self) -> Option<usize> { self.list.find_first_free() }
 
-  fn mark_free(&mut self, idx:usize) { self.list.set_leaf(idx, false).unwrap(); }
+  fn mark_free(&mut self, idx:usize) {
+    if self.is_reserved(idx) { self.occupied -= 1 }
+    self.list.set_leaf(idx, false).unwrap();
+    self.generations[idx] = self.generations[idx].wrapping_add(1);
+  }
 
-  fn mark_reserved(&mut self, idx:usize) { self.list.set_leaf(idx, true).unwrap(); }
+  fn mark_reserved(&mut self, idx:usize) {
+    if !self.is_reserved(idx) { self.occupied += 1 }
+    self.list.set_leaf(idx, true).unwrap();
+  }
+
+  fn is_current(&self, handle: Handle) -> bool {
+    self.is_reserved(handle.index) && self.generations[handle.index] == handle.generation
+  }
 
   #[must_use]
   fn reserve(&mut self) -> usize {
-    let pot_idx = self.first_free();
-    let idx = if pot_idx.is_some() { pot_idx.unwrap() }
+    let idx = if let Some(free) = self.first_free() { free }
     else {
       let old_len = self.len();
       self.resize(old_len + 1);
@@ -98,18 +246,78 @@ impl<T> Pond<T> {
     Self {
       data : Vec::new(),
       list: BinaryTree::new(),
+      generations: Vec::new(),
+      max_len: None,
+      occupied: 0,
+      warn_on_leak: false,
+      id: next_pond_id(),
     }
   }
 
+  /// Creates a new [Pond] which will never grow beyond `max_len` slots. See [Pond::set_max_len].
+  pub fn with_max_len(max_len: usize) -> Self {
+    let mut pool = Self::new();
+    pool.max_len = Some(max_len);
+    pool
+  }
+
+  /// Returns the maximum number of slots this pond may grow to, if one has been set.
+  pub fn max_len(&self) -> Option<usize> { self.max_len }
+
+  /// Caps the number of slots this pond may grow to, or lifts the cap with None.
+  /// Lowering the cap below [Pond::len] doesn't shrink anything, it only prevents further growth.
+  ///
+  /// Once capped, [Pond::try_alloc] fails instead of growing. Anything else which would grow
+  /// the pond past the cap ([Pond::alloc], [Pond::write], [Pond::resize]) panics.
+  pub fn set_max_len(&mut self, max_len: Option<usize>) { self.max_len = max_len }
+
+  /// Makes dropping the pond while it still holds data print the occupied indices to stderr.
+  /// Handy for catching forgotten [Pond::free]s, and a no-op in release builds.
+  pub fn set_leak_warning(&mut self, enabled: bool) { self.warn_on_leak = enabled }
+
   /// Returns the number of slots held internally, both free and full.
   pub fn len(&self) -> usize { self.data.len() }
 
+  /// Returns true if the pond holds no slots, free or full.
+  pub fn is_empty(&self) -> bool { self.data.is_empty() }
+
+  /// Returns the number of slots currently holding data.
+  pub fn occupied_count(&self) -> usize { self.occupied }
+
+  /// Returns the number of free slots within [Pond::len].
+  pub fn free_count(&self) -> usize { self.len() - self.occupied }
+
+  /// Returns the fraction of slots which are free, `free / len`, or 0.0 for an empty pond.
+  /// Handy for deciding when a [Pond::trim] is worth it. See [MemoryStats::fragmentation_ratio] for
+  /// the interior free slots as a share of capacity instead.
+  pub fn fragmentation(&self) -> f64 {
+    if self.is_empty() { return 0.0 }
+    self.free_count() as f64 / self.len() as f64
+  }
+
+  /// Returns every free index below [Pond::len] in ascending order. [Pond::next_allocated] is the first of these, if there are any.
+  pub fn free_indices(&self) -> Vec<usize> {
+    (0 .. self.len()).filter(|idx| !self.is_reserved(*idx)).collect()
+  }
+
   /// Returns the next index which will be allocated on a [Pond::alloc] call. If you need to
   /// guarantee a certain value, use [Pond::write] instead.
   pub fn next_allocated(&self) -> usize { self.first_free().unwrap_or(self.data.len()) }
 
+  /// Returns true if `idx` holds data. Out of bounds indices are never reserved.
+  pub fn contains(&self, idx: usize) -> bool { self.is_reserved(idx) }
+
+  /// Returns true if `idx` is free or out of bounds, the inverse of [Pond::contains].
+  pub fn is_free(&self, idx: usize) -> bool { !self.is_reserved(idx) }
+
   /// Sets Pond to hold `size` elements. If size < self.data().len(), excess data will be truncated and dropped.
+  /// Any slots added are free, so growing ahead of a bulk load avoids reallocating without changing what [Pond::alloc] hands out.
+  ///
+  /// Panics if growing to `size` would exceed [Pond::max_len].
   pub fn resize(&mut self, size: usize) {
+    if let Some(max_len) = self.max_len {
+      assert!(size <= self.len() || size <= max_len, "resizing to {size} exceeds the max_len of {max_len}");
+    }
     let additional = size.saturating_sub(self.data.len());
     while let Some(idx) = self.list.find_last_full() {
       if idx < size { break }
@@ -118,30 +326,121 @@ impl<T> Pond<T> {
     }
     self.data.reserve(additional);
     unsafe { self.data.set_len(size); }
+    if size > self.generations.len() { self.generations.resize(size, 0) }
 
     self.list.resize(size);
   }
 
-  /// Returns an immutable reference to the data stored at the requested index, or an [AccessError] if there is a problem.
+  /// Returns an immutable reference to the data stored at the requested index, or None if the slot is free.
   pub fn get(&self, idx:usize) -> Option<&T> {
     if !self.is_reserved(idx) { return None }
     Some( unsafe { self.data[idx].assume_init_ref() } )
   }
 
-  /// Returns a mutable reference to the data stored at the requested index, or an [AccessError] if there is a problem.
+  /// Returns a mutable reference to the data stored at the requested index, or None if the slot is free.
   pub fn get_mut(&mut self, idx:usize) -> Option<&mut T> {
     if !self.is_reserved(idx) { return None }
     Some( unsafe { self.data[idx].assume_init_mut() } )
   }
 
+  /// Returns a reference to the data at `idx` without checking it's in bounds or reserved.
+  ///
+  /// # Safety
+  /// `idx` must be less than [Pond::len] and hold data, ie [Pond::contains] would return true.
+  pub unsafe fn get_unchecked(&self, idx:usize) -> &T {
+    unsafe { self.data.get_unchecked(idx).assume_init_ref() }
+  }
+
+  /// Returns a mutable reference to the data at `idx` without checking it's in bounds or reserved.
+  ///
+  /// # Safety
+  /// `idx` must be less than [Pond::len] and hold data, ie [Pond::contains] would return true.
+  pub unsafe fn get_unchecked_mut(&mut self, idx:usize) -> &mut T {
+    unsafe { self.data.get_unchecked_mut(idx).assume_init_mut() }
+  }
+
+  /// [Pond::get]s every index in turn, returning the results in order. Unlike [Pond::get_disjoint_mut], repeats are fine.
+  pub fn get_many(&self, indices: &[usize]) -> Vec<Option<&T>> {
+    indices.iter().map(|idx| self.get(*idx)).collect()
+  }
+
+  /// Returns mutable references to several slots at once, or None if any of them is free or
+  /// any two indices are equal (which would alias). Mirrors [slice::get_disjoint_mut].
+  pub fn get_disjoint_mut<const N: usize>(&mut self, indices: [usize; N]) -> Option<[&mut T; N]> {
+    if indices.iter().any(|idx| !self.is_reserved(*idx)) { return None }
+    let slots = self.data.get_disjoint_mut(indices).ok()?;
+    Some( slots.map(|slot| unsafe { slot.assume_init_mut() }) )
+  }
+
+  /// [Pond::get_disjoint_mut] for the common two slot case, such as both ends of an edge.
+  pub fn get_pair_mut(&mut self, a:usize, b:usize) -> Option<(&mut T, &mut T)> {
+    let [a, b] = self.get_disjoint_mut([a, b])?;
+    Some( (a, b) )
+  }
+
   /// Stores `data` in PoolField, returning it's memory index.
+  ///
+  /// Panics if the pond is full and can't grow past [Pond::max_len], use [Pond::try_alloc] to handle that case.
   #[must_use]
   pub fn alloc(&mut self, data:T) -> usize {
     let idx = self.reserve();
     self.data[idx].write(data);
     idx
   }
+
+  /// [Pond::alloc]s the value built by `f`, which is handed the index it's about to be stored at.
+  ///
+  /// This lets self-referential data (say, a node whose children point back at their parent) know its own index
+  /// before it exists. The slot is only reserved once `f` returns, so a panic inside `f` leaves the pond untouched.
+  #[must_use]
+  pub fn alloc_with<F: FnOnce(usize) -> T>(&mut self, f:F) -> usize {
+    let idx = self.next_allocated();
+    let data = f(idx);
+    self.write(idx, data);
+    idx
+  }
+
+  /// [Pond::alloc]s `data` unless that would grow the pond past [Pond::max_len], in which case
+  /// `data` is handed back. Reusing a free slot under the cap always succeeds.
+  pub fn try_alloc(&mut self, data:T) -> Result<usize, T> {
+    let full = self.first_free().is_none() && self.max_len.is_some_and(|max_len| self.len() >= max_len);
+    if full { return Err(data) }
+    Ok( self.alloc(data) )
+  }
   
+  /// [Pond::alloc]s every item, returning their indices in the order they were given.
+  /// Capacity is reserved up front from the iterator's size hint.
+  #[must_use]
+  pub fn alloc_many<I: IntoIterator<Item = T>>(&mut self, items: I) -> Vec<usize> {
+    let items = items.into_iter();
+    let (lower, _) = items.size_hint();
+    self.data.reserve(lower);
+    let mut indices = Vec::with_capacity(lower);
+    for item in items { indices.push(self.alloc(item)) }
+    indices
+  }
+
+  /// Stores `values` in consecutive slots, returning the index of the first one. The i-th value lands at `base + i`.
+  ///
+  /// The first run of free slots long enough to fit everything is used, growing the pond at the end if none is.
+  /// Finding the run is a linear scan from the first free slot.
+  ///
+  /// `values` must not be empty, there would be no slot to return (this is only checked in debug builds).
+  /// Panics if the pond can't grow past [Pond::max_len], like [Pond::alloc].
+  #[must_use]
+  pub fn alloc_contiguous(&mut self, values: Vec<T>) -> usize {
+    debug_assert!(!values.is_empty(), "alloc_contiguous needs at least one value");
+    let mut base = self.next_allocated();
+    let mut idx = base;
+    while idx < self.len() && idx - base < values.len() {
+      if self.is_reserved(idx) { base = idx + 1 }
+      idx += 1;
+    }
+    if base + values.len() > self.len() { self.resize(base + values.len()) }
+    for (offset, value) in values.into_iter().enumerate() { self.write(base + offset, value); }
+    base
+  }
+
   /// Overwrite and reserve the data at `idx`. 
   /// Returns Some(old_data) or None, depending whether the slot was previously reserved.
   ///
@@ -150,12 +449,41 @@ impl<T> Pond<T> {
   pub fn write(&mut self, idx:usize, new_data:T) -> Option<T> {
     if idx >= self.len() { self.resize(idx + 1) }
     let old_value = if !self.is_reserved(idx) { None } 
-    else { Some( unsafe { self.data[idx].assume_init_read() } ) };
+    else {
+      self.generations[idx] = self.generations[idx].wrapping_add(1);
+      Some( unsafe { self.data[idx].assume_init_read() } )
+    };
     self.data[idx].write(new_data);
     self.mark_reserved(idx);
     old_value
   }
 
+  /// Runs `f` on the data at `idx`, returning its result or None if the slot is free.
+  pub fn update<R, F: FnOnce(&mut T) -> R>(&mut self, idx:usize, f:F) -> Option<R> {
+    self.get_mut(idx).map(f)
+  }
+
+  /// Replaces the data at `idx` with `f(&old)`, returning the old data or None if the slot is free.
+  /// Unlike [Pond::write] this is an in-place update, so handles to the slot remain valid.
+  pub fn replace_with<F: FnOnce(&T) -> T>(&mut self, idx:usize, f:F) -> Option<T> {
+    let slot = self.get_mut(idx)?;
+    let new_data = f(slot);
+    Some( std::mem::replace(slot, new_data) )
+  }
+
+  /// Returns the data at `idx`, first [Pond::write]ing the result of `f` there if the slot was free.
+  /// Returns None if `idx` is beyond [Pond::len], like [Pond::entry].
+  pub fn get_or_insert_with<F: FnOnce() -> T>(&mut self, idx:usize, f:F) -> Option<&mut T> {
+    Some( self.entry(idx)?.or_insert_with(f) )
+  }
+
+  /// Returns the [Entry] for `idx`, or None if it's out of bounds.
+  pub fn entry(&mut self, idx:usize) -> Option<Entry<'_, T>> {
+    if idx >= self.len() { return None }
+    if self.is_reserved(idx) { return Some( Entry::Occupied(unsafe { self.data[idx].assume_init_mut() }) ) }
+    Some( Entry::Vacant(VacantEntry { pond: self, idx }) )
+  }
+
   /// Frees the data at `index`, returning it on success or None on failure.
   /// Failure means you were trying to free a node which was already free.
   pub fn free(&mut self, idx:usize) -> Option<T> {
@@ -164,6 +492,105 @@ impl<T> Pond<T> {
     Some( unsafe { self.data[idx].assume_init_read() } )
   }
 
+  /// [Pond::free]s every index in turn, collecting each result in order.
+  /// Indices are processed sequentially, so freeing the same index twice yields None the second time.
+  pub fn free_many<I: IntoIterator<Item = usize>>(&mut self, indices: I) -> Vec<Option<T>> {
+    indices.into_iter().map(|idx| self.free(idx)).collect()
+  }
+
+  /// Swaps the contents of slots `a` and `b`, returning None if either is out of bounds.
+  /// Either slot may be free, in which case the live data is simply moved across.
+  pub fn swap(&mut self, a:usize, b:usize) -> Option<()> {
+    if a >= self.len() || b >= self.len() { return None }
+    if a == b { return Some(()) }
+    let (a_full, b_full) = (self.is_reserved(a), self.is_reserved(b));
+    self.data.swap(a, b);
+    if a_full { self.mark_free(a) }
+    if b_full { self.mark_free(b) }
+    if a_full { self.mark_reserved(b) }
+    if b_full { self.mark_reserved(a) }
+    Some(())
+  }
+
+  /// Frees (and drops) every occupied slot for which `f(idx, &data)` returns false, returning how many were freed.
+  /// Retained slots aren't moved.
+  pub fn retain<F: FnMut(usize, &T) -> bool>(&mut self, mut f: F) -> usize {
+    let mut freed = 0;
+    for idx in 0 .. self.len() {
+      let Some(data) = self.get(idx) else { continue };
+      if !f(idx, data) {
+        self.free(idx);
+        freed += 1;
+      }
+    }
+    freed
+  }
+
+  /// Moves the data at `from` into the free slot `to`, leaving `from` free.
+  /// Returns None without changing anything if `from` is free or `to` is occupied or out of bounds.
+  pub fn move_to(&mut self, from:usize, to:usize) -> Option<()> {
+    if !self.is_reserved(from) || to >= self.len() || self.is_reserved(to) { return None }
+    self.swap(from, to)
+  }
+
+  /// Frees every occupied slot which can't be reached from `roots`, returning the freed `(index, data)` pairs in ascending order.
+  ///
+  /// `neighbors` lists the indices a slot's data points at. Edges to free slots are ignored, and cycles are
+  /// fine, which makes this the way to reclaim structures that only reference each other.
+  pub fn collect_garbage<R, F>(&mut self, roots: R, neighbors: F) -> Vec<(usize, T)>
+  where R: IntoIterator<Item = usize>, F: Fn(&T) -> Vec<usize> {
+    let reachable = self.reachable_from(roots, neighbors);
+    let mut freed = Vec::new();
+    for idx in 0 .. self.len() {
+      if reachable.contains(&idx) { continue }
+      if let Some(data) = self.free(idx) { freed.push((idx, data)) }
+    }
+    freed
+  }
+
+  /// Returns every occupied slot reachable from `roots`, including the roots themselves, by following `neighbors`.
+  ///
+  /// `neighbors` lists the indices a slot's data points at. Free (or out of bounds) indices are skipped
+  /// rather than followed, and each slot is visited once, so cycles are fine.
+  pub fn reachable_from<R, F>(&self, roots: R, neighbors: F) -> HashSet<usize>
+  where R: IntoIterator<Item = usize>, F: Fn(&T) -> Vec<usize> {
+    let mut reachable = HashSet::new();
+    let mut stack: Vec<usize> = roots.into_iter().collect();
+    while let Some(idx) = stack.pop() {
+      let Some(data) = self.get(idx) else { continue };
+      if !reachable.insert(idx) { continue }
+      stack.extend(neighbors(data));
+    }
+    reachable
+  }
+
+  /// Moves every occupied slot of `other` into this pond with [Pond::alloc], returning where each one went. (Key:Old index in `other`, Value:New index in `self`)
+  ///
+  /// Existing data in this pond isn't touched, though its free slots will be filled first.
+  #[must_use]
+  pub fn merge(&mut self, mut other: Pond<T>) -> HashMap<usize, usize> {
+    let mut remapped = HashMap::with_capacity(other.occupied_count());
+    for (old, data) in other.drain() { remapped.insert(old, self.alloc(data)); }
+    remapped
+  }
+
+  /// Moves every occupied slot for which `f(idx, &data)` returns true out into a new pond, returning it
+  /// along with where each slot went. (Key:Old index in `self`, Value:New index in the returned pond)
+  ///
+  /// Slots are packed into the new pond in ascending order of their old index.
+  #[must_use]
+  pub fn split_off<F: FnMut(usize, &T) -> bool>(&mut self, mut f: F) -> (Pond<T>, HashMap<usize, usize>) {
+    let mut split = Pond::new();
+    let mut remapped = HashMap::new();
+    for idx in 0 .. self.len() {
+      let Some(data) = self.get(idx) else { continue };
+      if !f(idx, data) { continue }
+      let data = self.free(idx).unwrap();
+      remapped.insert(idx, split.alloc(data));
+    }
+    (split, remapped)
+  }
+
   /// Travels through memory and re-arranges slots so that they are contiguous in memory, with no free slots in between occupied ones.
   /// The hashmap returned can be used to remap your references to their new locations. (Key:Old, Value:New)
   /// 
@@ -176,18 +603,65 @@ impl<T> Pond<T> {
   #[must_use]
   pub fn defrag(&mut self) -> HashMap<usize, usize> {
     let mut remapped = HashMap::new();
-    if self.data.len() == 0 { return remapped }
-    'defrag: loop {
-      match (self.list.find_first_free(), self.list.find_last_full()) {
-        (Some(free), Some(full)) => {
-          if free >= full { break 'defrag }
-          remapped.insert(full, free);
-          self.data.swap(free, full);
-          self.list.set_leaf(full, false).unwrap();
-          self.list.set_leaf(free, true).unwrap();
-        }
-        _ => break 'defrag
-      }
+    self.defrag_with(|old, new| { remapped.insert(old, new); });
+    remapped
+  }
+
+  /// [Pond::defrag], calling `on_move(old, new)` for every relocated slot instead of building a hashmap.
+  pub fn defrag_with<F: FnMut(usize, usize)>(&mut self, on_move: F) { self.defrag_moves(usize::MAX, on_move); }
+
+  /// [Pond::defrag], pushing each `(old, new)` move onto `out` in the order the moves happened.
+  /// Useful when you only need to walk the remap once and would rather skip the hashmap.
+  pub fn defrag_into(&mut self, out: &mut Vec<(usize, usize)>) {
+    self.defrag_with(|old, new| out.push((old, new)));
+  }
+
+  /// Performs at most `max_moves` moves of [Pond::defrag], returning the partial remap and whether the pond is now fully compact.
+  ///
+  /// Calling this repeatedly (applying each remap as you go) spreads a defrag across several frames
+  /// and ends in exactly the same state as one [Pond::defrag] call. The pond is valid between calls.
+  #[must_use]
+  pub fn defrag_step(&mut self, max_moves: usize) -> (HashMap<usize, usize>, bool) {
+    let mut remapped = HashMap::new();
+    let done = self.defrag_moves(max_moves, |old, new| { remapped.insert(old, new); });
+    (remapped, done)
+  }
+
+  // The (full, free) pair defrag would move next, or None if the pond is already compact.
+  fn next_defrag_move(&self) -> Option<(usize, usize)> {
+    match (self.list.find_first_free(), self.list.find_last_full()) {
+      (Some(free), Some(full)) if free < full => Some((full, free)),
+      _ => None
+    }
+  }
+
+  // Performs up to `max_moves` defrag moves, returning whether the pond ended up compact.
+  fn defrag_moves<F: FnMut(usize, usize)>(&mut self, max_moves: usize, mut on_move: F) -> bool {
+    for _ in 0 .. max_moves {
+      let Some((full, free)) = self.next_defrag_move() else { return true };
+      on_move(full, free);
+      self.data.swap(free, full);
+      self.mark_free(full);
+      self.mark_reserved(free);
+    }
+    self.next_defrag_move().is_none()
+  }
+
+  /// Like [Pond::defrag], but slots keep their relative order: the k-th occupied slot ends up at index k.
+  /// The hashmap returned can be used to remap your references to their new locations. (Key:Old, Value:New)
+  ///
+  /// This shifts every slot after the first free one, so it's always O(N).
+  #[must_use]
+  pub fn defrag_stable(&mut self) -> HashMap<usize, usize> {
+    let mut remapped = HashMap::new();
+    let Some(mut next) = self.first_free() else { return remapped };
+    for idx in next + 1 .. self.len() {
+      if !self.is_reserved(idx) { continue }
+      remapped.insert(idx, next);
+      self.data.swap(next, idx);
+      self.mark_free(idx);
+      self.mark_reserved(next);
+      next += 1;
     }
     remapped
   }
@@ -200,6 +674,161 @@ impl<T> Pond<T> {
     remap
   }
 
+  /// Stores `data` like [Pond::alloc], returning a [Handle] instead of a raw index.
+  #[must_use]
+  pub fn alloc_handle(&mut self, data:T) -> Handle {
+    let idx = self.alloc(data);
+    Handle { index: idx, generation: self.generations[idx] }
+  }
+
+  /// Returns a [Handle] to the current occupant of `idx`, or None if the slot is free.
+  /// Use this to re-acquire handles after remapping indices through [Pond::defrag] or [Pond::trim].
+  pub fn handle(&self, idx:usize) -> Option<Handle> {
+    if !self.is_reserved(idx) { return None }
+    Some( Handle { index: idx, generation: self.generations[idx] } )
+  }
+
+  /// Turns a [Handle] back into a raw index, or None if its slot has been freed or reused since.
+  /// Handles don't keep anything alive, so [Pond::handle] doubles as a weak reference to a slot.
+  pub fn upgrade(&self, handle:Handle) -> Option<usize> {
+    if !self.is_current(handle) { return None }
+    Some( handle.index )
+  }
+
+  /// [Pond::get] through a [Handle], returning None if the handle is stale.
+  pub fn get_handle(&self, handle:Handle) -> Option<&T> {
+    if !self.is_current(handle) { return None }
+    self.get(handle.index)
+  }
+
+  /// [Pond::get_mut] through a [Handle], returning None if the handle is stale.
+  pub fn get_handle_mut(&mut self, handle:Handle) -> Option<&mut T> {
+    if !self.is_current(handle) { return None }
+    self.get_mut(handle.index)
+  }
+
+  /// [Pond::free] through a [Handle], returning None if the handle is stale.
+  pub fn free_handle(&mut self, handle:Handle) -> Option<T> {
+    if !self.is_current(handle) { return None }
+    self.free(handle.index)
+  }
+
+  /// Drops the free slots trailing the last occupied one and releases the spare capacity.
+  /// Unlike [Pond::trim] nothing is moved, so every index stays valid. If the last slot is occupied only capacity is released.
+  pub fn shrink_to_fit(&mut self) {
+    let size = self.list.find_last_full().map_or(0, |idx| idx + 1);
+    self.resize(size);
+    self.data.shrink_to_fit();
+  }
+
+  /// Returns a snapshot of how the pond's memory is being used. This doesn't walk the slots.
+  pub fn stats(&self) -> MemoryStats {
+    let used = self.list.find_last_full().map_or(0, |idx| idx + 1);
+    MemoryStats {
+      occupied: self.occupied,
+      free: self.free_count(),
+      len: self.len(),
+      interior_free: used - self.occupied,
+      capacity: self.data.capacity(),
+      bytes_for_data: self.data.capacity() * size_of::<T>(),
+      fragmentation: self.fragmentation(),
+    }
+  }
+
+  /// Checks the pond's internal bookkeeping is consistent, returning a description of the first problem found.
+  /// This walks every slot, so it's meant for `debug_assert!`s and tests rather than hot paths.
+  pub fn validate(&self) -> Result<(), String> {
+    if self.list.size() != self.data.len() {
+      return Err(format!("free tree tracks {} slots but {} are held", self.list.size(), self.data.len()))
+    }
+    if self.generations.len() < self.data.len() {
+      return Err(format!("only {} generations for {} slots", self.generations.len(), self.data.len()))
+    }
+    let occupied = (0 .. self.len()).filter(|idx| self.is_reserved(*idx)).count();
+    if occupied != self.occupied {
+      return Err(format!("{occupied} slots are reserved but {} are counted", self.occupied))
+    }
+    self.list.validate()
+  }
+
+  /// Builds a new pond holding `f(&data)` for every occupied slot, at the same index.
+  /// Free slots stay free, so every index (and [Handle]) carries over unchanged.
+  pub fn map<U, F: FnMut(&T) -> U>(&self, mut f: F) -> Pond<U> {
+    let mut data = Vec::with_capacity(self.data.len());
+    for idx in 0 .. self.data.len() {
+      data.push( match self.get(idx) {
+        Some(val) => MaybeUninit::new(f(val)),
+        None => MaybeUninit::uninit(),
+      });
+    }
+    Pond {
+      data,
+      list: self.list.clone(),
+      generations: self.generations.clone(),
+      max_len: self.max_len,
+      occupied: self.occupied,
+      warn_on_leak: self.warn_on_leak,
+      id: next_pond_id(),
+    }
+  }
+
+  /// Rolls the pond back to exactly the slots it held when `checkpoint` was taken, dropping anything stored since.
+  ///
+  /// Handles to slots whose occupant didn't change since the checkpoint stay valid, every other handle goes stale.
+  /// Restoring a checkpoint taken from a different pond copies its slots over and makes every handle stale.
+  pub fn restore(&mut self, checkpoint: Checkpoint<T>) {
+    let old = &checkpoint.pond;
+    // Generations from another pond say nothing about ours, so nothing counts as unchanged
+    let same_pond = checkpoint.origin == self.id;
+    let len = self.generations.len().max(old.generations.len());
+    let generations = (0 .. len).map(|idx| {
+      let current = self.generations.get(idx).copied();
+      let snapshot = old.generations.get(idx).copied();
+      let unchanged = same_pond && current == snapshot && old.is_reserved(idx) == self.is_reserved(idx);
+      let newest = current.max(snapshot).unwrap_or_default();
+      // Never hand a generation out twice, or a handle from after the checkpoint could match restored data
+      if unchanged { newest } else { newest.wrapping_add(1) }
+    }).collect();
+    // The replaced pond keeps its own generations until it's dropped, its Drop still frees through them
+    let mut replaced = std::mem::replace(self, checkpoint.pond);
+    self.generations = generations;
+    // Throwing away the current state on purpose isn't a leak
+    self.warn_on_leak = std::mem::take(&mut replaced.warn_on_leak);
+    self.id = replaced.id;
+    drop(replaced);
+  }
+
+  /// Builds a pond from raw slots, where `Some` slots are occupied and `None` slots are free, keeping every index.
+  /// Any layout is valid, so this can't fail.
+  pub fn from_slots(slots: Vec<Option<T>>) -> Self {
+    let mut pool = Self::new();
+    pool.resize(slots.len());
+    for (idx, slot) in slots.into_iter().enumerate() {
+      if let Some(data) = slot { pool.write(idx, data); }
+    }
+    pool
+  }
+
+  /// Tears the pond down into raw slots, the inverse of [Pond::from_slots]. Every index is preserved.
+  pub fn into_slots(mut self) -> Vec<Option<T>> {
+    (0 .. self.len()).map(|idx| self.free(idx)).collect()
+  }
+
+  /// Collects the occupied data into a vec in ascending index order, dropping the pond.
+  ///
+  /// Free slots are skipped rather than kept as gaps, so data only stays at the same index if the pond had no holes.
+  pub fn into_vec(mut self) -> Vec<T> {
+    let mut data = Vec::with_capacity(self.occupied);
+    data.extend( self.drain().map(|(_, val)| val) );
+    data
+  }
+
+  /// Returns an iterator which frees each occupied slot as it yields it, in ascending index order.
+  ///
+  /// Once the iterator is dropped the pond is left empty ([Pond::len] is 0) but keeps its capacity.
+  /// Like [Vec::drain], dropping it early still frees (and drops) anything it hasn't reached yet.
+  pub fn drain(&mut self) -> Drain<'_, T> { Drain { pond: self } }
+
   /// Returns a safe, readonly version of the allocated memory.
   pub fn safe_data(&self) -> Vec<Option<&T>> {
     let mut safe_data = Vec::with_capacity(self.data.len());
@@ -212,6 +841,133 @@ impl<T> Pond<T> {
   pub fn unsafe_data(&self) -> &Vec<MaybeUninit<T>> { &self.data }
 }
 
+/// Rewrites every index through a remap returned by [Pond::defrag], [Pond::trim] and friends.
+/// Indices missing from the remap (because they didn't move) are left alone.
+///
+/// Anything yielding `&mut usize` works, so `&mut [usize]`, `&mut Vec<usize>` and `&mut Option<usize>` can all be passed directly.
+pub fn apply_remap<'a, I: IntoIterator<Item = &'a mut usize>>(indices: I, remap: &HashMap<usize, usize>) {
+  for idx in indices {
+    if let Some(new) = remap.get(idx) { *idx = *new }
+  }
+}
+
+impl<T: Clone> Pond<T> {
+  /// Clones the data at `idx` out of the pond, or returns None if it's free.
+  pub fn get_cloned(&self, idx:usize) -> Option<T> { self.get(idx).cloned() }
+
+  /// Clones the data at `idx` into a freshly [Pond::alloc]ed slot, returning the new index
+  /// or None if `idx` is free. The original slot is untouched.
+  #[must_use]
+  pub fn duplicate(&mut self, idx:usize) -> Option<usize> {
+    let copy = self.get(idx)?.clone();
+    Some( self.alloc(copy) )
+  }
+
+  /// Snapshots the pond so it can be rolled back with [Pond::restore].
+  pub fn checkpoint(&self) -> Checkpoint<T> {
+    let mut pond = self.clone();
+    // The snapshot is dropped whole when it's restored or discarded, so it never warns
+    pond.warn_on_leak = false;
+    Checkpoint { pond, origin: self.id }
+  }
+
+  /// Clones every occupied slot out as `(index, data)` pairs, in ascending index order.
+  pub fn to_vec(&self) -> Vec<(usize, T)> {
+    let mut occupied = Vec::with_capacity(self.occupied);
+    for idx in 0 .. self.len() {
+      if let Some(data) = self.get(idx) { occupied.push((idx, data.clone())) }
+    }
+    occupied
+  }
+}
+
+impl<T> Drop for Pond<T> {
+  fn drop(&mut self) {
+    #[cfg(debug_assertions)]
+    if self.warn_on_leak && self.occupied != 0 {
+      let leaked: Vec<usize> = (0 .. self.len()).filter(|&idx| self.is_reserved(idx)).collect();
+      eprintln!("Pond dropped with {} occupied slots: {leaked:?}", self.occupied);
+    }
+    if !std::mem::needs_drop::<T>() { return }
+    while let Some(idx) = self.list.find_first_full() { drop(self.free(idx)) }
+  }
+}
+
+impl<T> Default for Pond<T> {
+  fn default() -> Self { Self::new() }
+}
+
+/// Panics if `idx` is free or out of bounds, where [Pond::get] would return None.
+impl<T> std::ops::Index<usize> for Pond<T> {
+  type Output = T;
+  fn index(&self, idx: usize) -> &T {
+    self.get(idx).unwrap_or_else(|| panic!("index {idx} is free or out of bounds"))
+  }
+}
+
+/// Panics if `idx` is free or out of bounds, where [Pond::get_mut] would return None.
+impl<T> std::ops::IndexMut<usize> for Pond<T> {
+  fn index_mut(&mut self, idx: usize) -> &mut T {
+    self.get_mut(idx).unwrap_or_else(|| panic!("index {idx} is free or out of bounds"))
+  }
+}
+
+/// Deep copies every reserved slot, leaving free slots free at the same indices.
+impl<T: Clone> Clone for Pond<T> {
+  fn clone(&self) -> Self { self.map(T::clone) }
+}
+
+/// Prints a summary of the pond followed by its occupied slots only, as an `index: data` map.
+impl<T: std::fmt::Debug> std::fmt::Debug for Pond<T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    struct Occupied<'a, T>(&'a Pond<T>);
+    impl<T: std::fmt::Debug> std::fmt::Debug for Occupied<'_, T> {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pond = self.0;
+        f.debug_map().entries( (0 .. pond.len()).filter_map(|idx| Some((idx, pond.get(idx)?))) ).finish()
+      }
+    }
+    f.debug_struct("Pond")
+      .field("occupied", &self.occupied_count())
+      .field("free", &self.free_count())
+      .field("capacity", &self.data.capacity())
+      .field("slots", &Occupied(self))
+      .finish()
+  }
+}
+
+/// Item i of the vec lands at index i, with no free slots.
+impl<T> From<Vec<T>> for Pond<T> {
+  fn from(data: Vec<T>) -> Self { data.into_iter().collect() }
+}
+
+/// Two ponds are equal when every index holds equal data in both, or is free in both.
+/// Capacity, trailing free slots, generations and [Pond::max_len] are ignored.
+impl<T: PartialEq> PartialEq for Pond<T> {
+  fn eq(&self, other: &Self) -> bool {
+    (0 .. self.len().max(other.len())).all(|idx| self.get(idx) == other.get(idx))
+  }
+}
+
+/// Allocates each item in order, so the i-th item lands at index i.
+/// The pond is pre-sized from the iterator's size hint.
+impl<T> FromIterator<T> for Pond<T> {
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let mut pool = Self::new();
+    pool.extend(iter);
+    pool
+  }
+}
+
+/// [Pond::alloc]s each item in turn, so free slots are filled (front to back) before
+/// anything is appended to the end. Contiguous placement is only guaranteed if the pond has no free slots.
+impl<T> Extend<T> for Pond<T> {
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    let iter = iter.into_iter();
+    self.data.reserve(iter.size_hint().0);
+    for item in iter { let _ = self.alloc(item); }
+  }
+}
 
 use serde::{Serialize, Serializer, ser::SerializeSeq, Deserialize, Deserializer};
 impl<T> Serialize for Pond<T> where T: Serialize {
@@ -224,11 +980,39 @@ impl<T> Serialize for Pond<T> where T: Serialize {
 impl<'de, T> Deserialize<'de> for Pond<T> where T: Deserialize<'de> {
   fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
     let data: Vec<Option<T>> = Vec::deserialize(deserializer)?;
-    let mut pool = Self::new();
-    pool.resize(data.len());
-    for (idx, pot_val) in data.into_iter().enumerate() {
-      if let Some(val) = pot_val { pool.write(idx, val); }
-    }
-    Ok(pool)
+    Ok(Self::from_slots(data))
   }
 }
+
+#[test]
+fn validate() {
+  let mut pool: Pond<_> = (0..6).collect();
+  pool.free(2);
+  assert!(pool.validate().is_ok());
+
+  // Reserve a slot behind the counter's back
+  let mut counted = pool.clone();
+  counted.list.set_leaf(2, true);
+  assert!(counted.validate().unwrap_err().contains("counted"));
+
+  let mut generations = pool.clone();
+  generations.generations.truncate(3);
+  assert!(generations.validate().unwrap_err().contains("generations"));
+
+  let mut tracked = pool.clone();
+  tracked.list.resize(4);
+  assert!(tracked.validate().unwrap_err().contains("free tree"));
+}
+
+#[test]
+fn checkpoint_leak_warning() {
+  let mut pool: Pond<_> = (0..3).collect();
+  pool.set_leak_warning(true);
+  let checkpoint = pool.checkpoint();
+  assert!(!checkpoint.pond.warn_on_leak);
+  pool.restore(checkpoint);
+  // The restored pond keeps the caller's setting
+  assert!(pool.warn_on_leak);
+  // Empty it so the end of the test stays quiet
+  pool.resize(0);
+}
@@ -0,0 +1,24 @@
+//! Parallel iteration with the `rayon` crate, behind the `rayon` feature.
+use crate::Pond;
+use rayon::prelude::*;
+
+impl<T: Sync> Pond<T> {
+  /// Iterates over the occupied slots as `(index, data)` pairs in parallel, skipping free ones.
+  pub fn par_iter(&self) -> impl ParallelIterator<Item = (usize, &T)> + '_ {
+    self.data.par_iter().enumerate().filter_map(|(idx, slot)| {
+      if !self.is_reserved(idx) { return None }
+      Some( (idx, unsafe { slot.assume_init_ref() }) )
+    })
+  }
+}
+
+impl<T: Send> Pond<T> {
+  /// Like [Pond::par_iter], but hands out mutable references.
+  pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (usize, &mut T)> + '_ {
+    let list = &self.list;
+    self.data.par_iter_mut().enumerate().filter_map(move |(idx, slot)| {
+      if !list.is_full(idx).unwrap_or_default() { return None }
+      Some( (idx, unsafe { slot.assume_init_mut() }) )
+    })
+  }
+}
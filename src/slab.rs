@@ -0,0 +1,17 @@
+//! Conversions to and from the `slab` crate's [Slab], behind the `slab` feature.
+use crate::Pond;
+use slab::Slab;
+
+/// Every slab entry becomes an occupied slot at the same index as its key.
+impl<T> From<Slab<T>> for Pond<T> {
+  fn from(slab: Slab<T>) -> Self {
+    let mut pond = Pond::new();
+    for (key, value) in slab { pond.write(key, value); }
+    pond
+  }
+}
+
+impl<T: Clone> Pond<T> {
+  /// Clones the pond into a [Slab], keeping every occupied slot at the same key.
+  pub fn to_slab(&self) -> Slab<T> { self.to_vec().into_iter().collect() }
+}
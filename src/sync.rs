@@ -0,0 +1,59 @@
+use crate::Pond;
+use std::ops::Deref;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A [Pond] behind an [RwLock], so it can be shared between threads with an `Arc`.
+///
+/// Every call takes the lock for its own duration, anything more involved can grab the
+/// whole pond with [SyncPond::lock_read] or [SyncPond::lock_write]. Indices stay stable across threads
+/// as long as nobody runs [Pond::defrag], [Pond::trim] or friends under [SyncPond::lock_write].
+#[derive(Debug)]
+pub struct SyncPond<T> {
+  pond: RwLock<Pond<T>>,
+}
+impl<T> SyncPond<T> {
+  /// Creates a new, empty [SyncPond].
+  pub fn new() -> Self { Self { pond: RwLock::new(Pond::new()) } }
+
+  /// Locks the pond for reading, blocking until no writer holds it.
+  pub fn lock_read(&self) -> RwLockReadGuard<'_, Pond<T>> { self.pond.read().expect("SyncPond lock poisoned") }
+
+  /// Locks the pond for writing, blocking until every other guard is released.
+  pub fn lock_write(&self) -> RwLockWriteGuard<'_, Pond<T>> { self.pond.write().expect("SyncPond lock poisoned") }
+
+  /// [Pond::alloc]s `data` under the write lock.
+  #[must_use]
+  pub fn alloc(&self, data: T) -> usize { self.lock_write().alloc(data) }
+
+  /// [Pond::get]s the data at `idx`, holding the read lock until the returned [PondRef] is dropped.
+  pub fn get(&self, idx: usize) -> Option<PondRef<'_, T>> {
+    let guard = self.lock_read();
+    if !guard.contains(idx) { return None }
+    Some( PondRef { guard, idx } )
+  }
+
+  /// [Pond::free]s the data at `idx` under the write lock.
+  pub fn free(&self, idx: usize) -> Option<T> { self.lock_write().free(idx) }
+
+  /// Unwraps the inner [Pond].
+  pub fn into_inner(self) -> Pond<T> { self.pond.into_inner().expect("SyncPond lock poisoned") }
+}
+
+impl<T> Default for SyncPond<T> {
+  fn default() -> Self { Self::new() }
+}
+
+impl<T> From<Pond<T>> for SyncPond<T> {
+  fn from(pond: Pond<T>) -> Self { Self { pond: RwLock::new(pond) } }
+}
+
+/// A reference to a slot in a [SyncPond], keeping the pond read-locked while it lives.
+pub struct PondRef<'a, T> {
+  guard: RwLockReadGuard<'a, Pond<T>>,
+  idx: usize,
+}
+impl<T> Deref for PondRef<'_, T> {
+  type Target = T;
+  // The slot was checked when the guard was taken, and nobody can free it while we hold the lock.
+  fn deref(&self) -> &T { self.guard.get(self.idx).unwrap() }
+}
@@ -0,0 +1,55 @@
+use lilypads::Pond;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Graph {
+  #[serde(with = "lilypads::compact")]
+  nodes: Pond<u32>,
+}
+
+fn parse(json: &str) -> Result<Pond<u32>, serde_json::Error> {
+  serde_json::from_str::<Graph>(json).map(|graph| graph.nodes)
+}
+
+#[test]
+fn round_trip() {
+  let mut nodes: Pond<u32> = (0..6).collect();
+  nodes.free_many([0, 2, 3]);
+  let json = serde_json::to_string(&Graph { nodes: nodes.clone() }).unwrap();
+  assert_eq!(json, r#"{"nodes":{"len":6,"slots":[[1,1],[4,4],[5,5]]}}"#);
+
+  let copy = parse(&json).unwrap();
+  assert_eq!(copy.safe_data(), nodes.safe_data());
+  assert!(copy.validate().is_ok());
+}
+
+#[test]
+fn trailing_free_slots() {
+  let mut nodes: Pond<u32> = (0..4).collect();
+  nodes.free(3);
+  let json = serde_json::to_string(&Graph { nodes: nodes.clone() }).unwrap();
+  // Indices survive, the free tail doesn't
+  let copy = parse(&json).unwrap();
+  assert_eq!(copy.len(), 3);
+  assert_eq!(copy, nodes);
+}
+
+#[test]
+fn malformed() {
+  assert!(parse(r#"{"nodes":{"len":2,"slots":[[2,0]]}}"#).unwrap_err().to_string().contains("outside"));
+  assert!(parse(r#"{"nodes":{"len":2,"slots":[[1,0],[1,0]]}}"#).unwrap_err().to_string().contains("twice"));
+  // A huge length alone allocates nothing
+  assert_eq!(parse(r#"{"nodes":{"len":18446744073709551615,"slots":[]}}"#).unwrap().len(), 0);
+  // But a huge index is rejected before anything is allocated
+  assert!(parse(r#"{"nodes":{"len":18446744073709551615,"slots":[[18446744073709551614,0]]}}"#).unwrap_err().to_string().contains("limit"));
+  assert!(parse(r#"{"nodes":{"len":18446744073709551615,"slots":[[8589934592,0]]}}"#).unwrap_err().to_string().contains("limit"));
+}
+
+#[test]
+fn bounded() {
+  let json = r#"{"len":8,"slots":[[3,1]]}"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  assert_eq!(lilypads::compact::deserialize_bounded::<u32, _>(&mut deserializer, 4).unwrap().len(), 4);
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  assert!(lilypads::compact::deserialize_bounded::<u32, _>(&mut deserializer, 3).is_err());
+}
@@ -1,4 +1,4 @@
-use lilypads::Pond;
+use lilypads::{Pond, apply_remap};
 
 #[test]
 fn alloc() {
@@ -10,6 +10,118 @@ fn alloc() {
   assert_eq!(*pool.get(idx2).unwrap(), 123);
 }
 
+#[test]
+fn alloc_many() {
+  let mut pool = Pond::new();
+  let first = pool.alloc(0);
+  let _second = pool.alloc(1);
+  pool.free(first);
+
+  // The hole is filled first, then the rest is appended
+  let indices = pool.alloc_many([10, 20, 30]);
+  assert_eq!(indices, vec![0, 2, 3]);
+  for (idx, val) in indices.iter().zip([10, 20, 30]) { assert_eq!(*pool.get(*idx).unwrap(), val) }
+}
+
+#[test]
+fn alloc_with() {
+  let mut pool = Pond::new();
+  let _ = pool.alloc((0, "root"));
+  let hole = pool.alloc((1, "hole"));
+  let _ = pool.alloc((2, "tail"));
+  pool.free(hole);
+
+  // The closure learns its index before the data exists
+  let idx = pool.alloc_with(|idx| (idx, "self"));
+  assert_eq!(idx, hole);
+  assert_eq!(*pool.get(idx).unwrap(), (hole, "self"));
+
+  let idx = pool.alloc_with(|idx| (idx, "appended"));
+  assert_eq!(*pool.get(idx).unwrap(), (3, "appended"));
+}
+
+#[test]
+fn try_alloc() {
+  let mut pool = Pond::with_max_len(2);
+  let idx1 = pool.try_alloc(1).unwrap();
+  let _idx2 = pool.try_alloc(2).unwrap();
+  // Growing past the cap hands the data back
+  assert_eq!(pool.try_alloc(3), Err(3));
+  assert_eq!(pool.len(), 2);
+
+  // A free slot under the cap can still be reused
+  pool.free(idx1);
+  assert_eq!(pool.try_alloc(4), Ok(idx1));
+
+  // Lifting the cap allows growth again
+  pool.set_max_len(None);
+  assert_eq!(pool.try_alloc(5), Ok(2));
+}
+
+#[test]
+#[should_panic(expected = "exceeds the max_len of 1")]
+fn max_len_alloc() {
+  let mut pool = Pond::with_max_len(1);
+  let _ = pool.alloc(1);
+  let _ = pool.alloc(2);
+}
+
+#[test]
+fn resize() {
+  let mut pool = Pond::<i32>::new();
+  pool.resize(10);
+  // The new slots are free, so the pond is still logically empty
+  assert_eq!(pool.len(), 10);
+  assert_eq!(pool.stats().occupied, 0);
+  assert_eq!(pool.next_allocated(), 0);
+  assert_eq!(pool.alloc(1), 0);
+
+  // Shrinking drops anything beyond the new length
+  pool.write(8, 8);
+  pool.resize(5);
+  assert_eq!(pool.len(), 5);
+  assert_eq!(pool.get(8), None);
+  assert_eq!(pool.stats().occupied, 1);
+}
+
+#[test]
+fn alloc_contiguous() {
+  let mut pool: Pond<_> = (0..8).collect();
+  // Leave a hole of one and a hole of three
+  pool.free(1);
+  pool.free(3);
+  pool.free(4);
+  pool.free(5);
+
+  // The first hole is too small, so the second one is used
+  let base = pool.alloc_contiguous(vec![30, 40, 50]);
+  assert_eq!(base, 3);
+  for (offset, val) in [30, 40, 50].into_iter().enumerate() { assert_eq!(*pool.get(base + offset).unwrap(), val) }
+  assert!(pool.is_free(1));
+
+  // Nothing left fits, so the run is appended to the end
+  let base = pool.alloc_contiguous(vec![80, 90]);
+  assert_eq!(base, 8);
+  assert_eq!(pool.len(), 10);
+  assert_eq!(*pool.get(9).unwrap(), 90);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "needs at least one value")]
+fn alloc_contiguous_empty() {
+  let _ = Pond::<u32>::new().alloc_contiguous(Vec::new());
+}
+
+#[test]
+fn alloc_contiguous_tail() {
+  let mut pool: Pond<_> = (0..3).collect();
+  pool.free(2);
+  // A free run at the very end is extended rather than skipped
+  assert_eq!(pool.alloc_contiguous(vec![1, 2, 3]), 2);
+  assert_eq!(pool.len(), 5);
+}
+
 #[test]
 fn get() {
   let mut pool = Pond::new();
@@ -19,6 +131,35 @@ fn get() {
   assert_eq!(pool.get(idx + 1), None);
 }
 
+#[test]
+fn contains() {
+  let mut pool = Pond::new();
+  let idx1 = pool.alloc(42);
+  let idx2 = pool.alloc(43);
+  pool.free(idx1);
+
+  // Live slots
+  assert!(pool.contains(idx2));
+  assert!(!pool.is_free(idx2));
+  // Freed slots
+  assert!(!pool.contains(idx1));
+  assert!(pool.is_free(idx1));
+  // Out of bounds
+  assert!(!pool.contains(100));
+  assert!(pool.is_free(100));
+}
+
+#[test]
+fn get_unchecked() {
+  let mut pool = Pond::new();
+  let idx = pool.alloc(42);
+  assert!(pool.contains(idx));
+  unsafe {
+    *pool.get_unchecked_mut(idx) += 1;
+    assert_eq!(*pool.get_unchecked(idx), 43);
+  }
+}
+
 #[test]
 fn mut_get() {
   let mut pool = Pond::new();
@@ -28,6 +169,75 @@ fn mut_get() {
   assert_eq!(*pool.get(idx).unwrap(), 13);
 }
 
+#[test]
+fn get_many() {
+  let mut pool: Pond<_> = (0..3).collect();
+  pool.free(1);
+  assert_eq!(pool.get_many(&[2, 1, 0, 2, 5]), vec![Some(&2), None, Some(&0), Some(&2), None]);
+}
+
+#[test]
+fn get_disjoint_mut() {
+  let mut pool = Pond::new();
+  let idx1 = pool.alloc(1);
+  let idx2 = pool.alloc(2);
+  let idx3 = pool.alloc(3);
+  pool.free(idx3);
+
+  // Disjoint live slots can be borrowed together
+  let [a, b] = pool.get_disjoint_mut([idx1, idx2]).unwrap();
+  std::mem::swap(a, b);
+  assert_eq!(*pool.get(idx1).unwrap(), 2);
+  assert_eq!(*pool.get(idx2).unwrap(), 1);
+
+  // Aliasing and free slots are rejected
+  assert!(pool.get_disjoint_mut([idx1, idx1]).is_none());
+  assert!(pool.get_disjoint_mut([idx1, idx3]).is_none());
+  assert!(pool.get_disjoint_mut([idx1, 10]).is_none());
+}
+
+#[test]
+fn index_ops() {
+  let mut pool = Pond::new();
+  let idx = pool.alloc(42);
+  assert_eq!(pool[idx], 42);
+  pool[idx] = 13;
+  assert_eq!(pool[idx], 13);
+}
+
+#[test]
+#[should_panic(expected = "index 1 is free or out of bounds")]
+fn index_free() {
+  let mut pool = Pond::new();
+  let _ = pool.alloc(42);
+  let _ = pool[1];
+}
+
+#[test]
+#[should_panic(expected = "index 0 is free or out of bounds")]
+fn index_mut_free() {
+  let mut pool = Pond::new();
+  let idx = pool.alloc(42);
+  pool.free(idx);
+  pool[idx] = 1;
+}
+
+#[test]
+fn duplicate() {
+  let mut pool = Pond::new();
+  let idx = pool.alloc(vec![1, 2, 3]);
+  let copy = pool.duplicate(idx).unwrap();
+  assert_ne!(idx, copy);
+
+  // The copy is independent of the source
+  pool.get_mut(copy).unwrap().push(4);
+  assert_eq!(*pool.get(idx).unwrap(), vec![1, 2, 3]);
+  assert_eq!(*pool.get(copy).unwrap(), vec![1, 2, 3, 4]);
+
+  pool.free(idx);
+  assert_eq!(pool.duplicate(idx), None);
+}
+
 #[test] 
 fn free() {
   let mut pool = Pond::new();
@@ -41,6 +251,78 @@ fn free() {
   assert_eq!(pool.free(idx), None);
 }
 
+#[test]
+fn free_many() {
+  let mut pool: Pond<_> = (0..4).collect();
+  let freed = pool.free_many([1, 3, 1, 9]);
+  // Live slots hand back their data, a repeated or out of bounds index gets None
+  assert_eq!(freed, vec![Some(1), Some(3), None, None]);
+  assert!(pool.contains(0) && pool.contains(2));
+  assert!(pool.is_free(1) && pool.is_free(3));
+}
+
+#[test]
+fn retain() {
+  let mut pool: Pond<_> = (0..10).collect();
+  pool.free(2);
+  // Drop every odd value, plus a slot picked by index
+  let freed = pool.retain(|idx, val| val % 2 == 0 && idx != 8);
+  assert_eq!(freed, 6);
+  let kept: Vec<_> = (0..10).filter(|idx| pool.contains(*idx)).collect();
+  assert_eq!(kept, vec![0, 4, 6]);
+  assert_eq!(*pool.get(6).unwrap(), 6);
+}
+
+#[test]
+fn drain() {
+  let mut pool: Pond<_> = (0..6).collect();
+  pool.free(1);
+  let drained: Vec<_> = pool.drain().collect();
+  assert_eq!(drained, vec![(0, 0), (2, 2), (3, 3), (4, 4), (5, 5)]);
+  // The pond is empty and ready for reuse
+  assert_eq!(pool.len(), 0);
+  assert_eq!(pool.alloc(10), 0);
+}
+
+#[test]
+fn drain_partial() {
+  let mut pool: Pond<_> = (0..6).collect();
+  let first_two: Vec<_> = pool.drain().take(2).collect();
+  assert_eq!(first_two, vec![(0, 0), (1, 1)]);
+  // Stopping early still frees the rest
+  assert_eq!(pool.len(), 0);
+  assert_eq!(pool.get(2), None);
+}
+
+#[test]
+fn reachable_from() {
+  let mut pool: Pond<Vec<usize>> = Pond::new();
+  let a = pool.alloc(vec![1]);
+  let b = pool.alloc(vec![0, 2, 9]); // Cycles back to a, and dangles into free slots
+  let c = pool.alloc(vec![]);
+  let _d = pool.alloc(vec![0]);
+  let e = pool.alloc(vec![]);
+  pool.free(e);
+
+  let reachable = pool.reachable_from([a, e], |edges| edges.clone());
+  assert_eq!(reachable, [a, b, c].into_iter().collect());
+}
+
+#[test]
+fn collect_garbage() {
+  // Each node lists the indices it points at
+  let mut pool: Pond<Vec<usize>> = Pond::new();
+  let root = pool.alloc(vec![1]);
+  let _child = pool.alloc(vec![0, 7]); // Points back at the root, and at a free slot
+  let orphan_a = pool.alloc(vec![3]);
+  let orphan_b = pool.alloc(vec![2]); // An unreachable cycle
+
+  let freed = pool.collect_garbage([root], |edges| edges.clone());
+  assert_eq!(freed, vec![(orphan_a, vec![3]), (orphan_b, vec![2])]);
+  assert!(pool.contains(0) && pool.contains(1));
+  assert_eq!(pool.occupied_count(), 2);
+}
+
 #[test]
 fn write() {
   let mut pool = Pond::new();
@@ -57,6 +339,309 @@ fn write() {
   assert_eq!(*pool.get(idx2).unwrap(), 29);
 }
 
+#[test]
+fn from_iter() {
+  let pool: Pond<_> = vec![10, 20, 30].into_iter().collect();
+  // Items are placed in order with no free slots in between
+  assert_eq!(pool.len(), 3);
+  assert_eq!(*pool.get(0).unwrap(), 10);
+  assert_eq!(*pool.get(1).unwrap(), 20);
+  assert_eq!(*pool.get(2).unwrap(), 30);
+}
+
+#[test]
+fn collect_range() {
+  let pool: Pond<i32> = (0..100).collect();
+  assert_eq!(pool.len(), 100);
+  for i in 0..100 { assert_eq!(*pool.get(i as usize).unwrap(), i) }
+}
+
+#[test]
+fn extend() {
+  let mut pool: Pond<_> = vec![10, 20].into_iter().collect();
+  pool.extend([30, 40]);
+  // New items are appended after the existing ones
+  assert_eq!(pool.len(), 4);
+  assert_eq!(*pool.get(2).unwrap(), 30);
+  assert_eq!(*pool.get(3).unwrap(), 40);
+}
+
+#[test]
+fn clone() {
+  let mut pool: Pond<_> = (0..5).map(|i| i.to_string()).collect();
+  pool.free(1);
+  pool.free(3);
+
+  let mut copy = pool.clone();
+  assert_eq!(copy.safe_data(), pool.safe_data());
+  assert_eq!(copy.next_allocated(), 1);
+
+  // The copy is independent of the original
+  *copy.get_mut(0).unwrap() = "changed".to_string();
+  copy.free(2);
+  assert_eq!(pool.get(0).unwrap(), "0");
+  assert_eq!(pool.get(2).unwrap(), "2");
+}
+
+#[test]
+fn extend_reuse() {
+  let mut pool: Pond<_> = (0..3).collect();
+  pool.free(1);
+  pool.extend([10, 11, 12]);
+  // The freed middle slot is filled before anything is appended
+  assert_eq!(*pool.get(1).unwrap(), 10);
+  assert_eq!(*pool.get(3).unwrap(), 11);
+  assert_eq!(*pool.get(4).unwrap(), 12);
+}
+
+#[test]
+fn update() {
+  let mut pool = Pond::new();
+  let idx = pool.alloc(vec![1, 2]);
+  // The closure mutates in place and its result is passed through
+  assert_eq!(pool.update(idx, |list| { list.push(3); list.len() }), Some(3));
+  assert_eq!(*pool.get(idx).unwrap(), vec![1, 2, 3]);
+
+  pool.free(idx);
+  assert_eq!(pool.update(idx, |list| list.len()), None);
+}
+
+#[test]
+fn replace_with() {
+  let mut pool = Pond::new();
+  let idx = pool.alloc(41);
+  assert_eq!(pool.replace_with(idx, |count| count + 1), Some(41));
+  assert_eq!(*pool.get(idx).unwrap(), 42);
+
+  // Free slots never run the closure
+  pool.free(idx);
+  assert_eq!(pool.replace_with(idx, |_| unreachable!()), None);
+}
+
+#[test]
+fn get_or_insert_with() {
+  let mut pool = Pond::new();
+  let idx = pool.alloc(1);
+
+  // Occupied slots are returned untouched and the closure never runs
+  *pool.get_or_insert_with(idx, || unreachable!()).unwrap() += 1;
+  assert_eq!(*pool.get(idx).unwrap(), 2);
+
+  // Free slots are filled in
+  pool.resize(3);
+  assert_eq!(*pool.get_or_insert_with(2, || 7).unwrap(), 7);
+  assert_eq!(*pool.get(2).unwrap(), 7);
+
+  // Slots beyond the current length are out of range
+  assert!(pool.get_or_insert_with(5, || unreachable!()).is_none());
+  assert_eq!(pool.len(), 3);
+}
+
+#[test]
+fn map() {
+  let mut pool: Pond<i32> = (0..4).collect();
+  pool.free(1);
+  let handle = pool.handle(2).unwrap();
+
+  let strings = pool.map(|val| format!("node {val}"));
+  assert_eq!(strings.len(), pool.len());
+  assert_eq!(strings.get(1), None);
+  assert_eq!(strings.get(3).unwrap(), "node 3");
+  assert_eq!(strings.get_handle(handle).unwrap(), "node 2");
+  assert_eq!(strings.next_allocated(), 1);
+}
+
+#[test]
+fn eq() {
+  let mut pool: Pond<_> = (0..4).collect();
+  pool.free(1);
+  let mut other = pool.clone();
+  other.resize(20);
+  // Trailing free slots don't matter
+  assert_eq!(pool, other);
+
+  // Data moving to a different index does
+  let _ = other.defrag();
+  assert_ne!(pool, other);
+}
+
+#[test]
+fn eq_clone() {
+  let mut pool: Pond<_> = (0..4).collect();
+  let mut copy = pool.clone();
+  assert_eq!(pool, copy);
+  // A single changed value or freed slot breaks equality
+  copy.write(2, 20);
+  assert_ne!(pool, copy);
+  copy.write(2, 2);
+  pool.free(3);
+  assert_ne!(pool, copy);
+}
+
+#[test]
+fn to_vec() {
+  let mut pool: Pond<_> = ["a", "b", "c", "d"].into_iter().map(String::from).collect();
+  pool.free(0);
+  pool.free(2);
+  assert_eq!(pool.to_vec(), vec![(1, "b".to_string()), (3, "d".to_string())]);
+}
+
+#[test]
+fn debug() {
+  let mut pool: Pond<_> = (10..15).collect();
+  for idx in [1, 2, 4] { pool.free(idx); }
+  let printed = format!("{pool:?}");
+  assert!(printed.starts_with("Pond { occupied: 2, free: 3, capacity: "));
+  assert!(printed.ends_with("slots: {0: 10, 3: 13} }"));
+}
+
+#[test]
+fn checkpoint() {
+  let mut pool: Pond<_> = (0..4).collect();
+  pool.free(1);
+  let kept = pool.handle(0).unwrap();
+  let freed_later = pool.handle(2).unwrap();
+  let before = pool.clone();
+  let checkpoint = pool.checkpoint();
+
+  pool.free(2);
+  let added = pool.alloc_handle(10);
+  let _ = pool.alloc(11);
+  *pool.get_mut(3).unwrap() = 30;
+
+  pool.restore(checkpoint);
+  assert_eq!(pool, before);
+  assert_eq!(pool.next_allocated(), 1);
+  // Untouched occupants keep their handles, nothing from after the checkpoint resolves
+  assert_eq!(*pool.get_handle(kept).unwrap(), 0);
+  assert_eq!(pool.get_handle(freed_later), None);
+  assert_eq!(pool.get_handle(added), None);
+  let _ = pool.alloc(12);
+  assert_eq!(pool.get_handle(added), None);
+}
+
+#[test]
+fn checkpoint_drops_data() {
+  let mut pool: Pond<String> = ["a", "b", "c"].into_iter().map(String::from).collect();
+  let checkpoint = pool.checkpoint();
+  pool.free(0);
+  *pool.get_mut(1).unwrap() = String::from("changed");
+  let _ = pool.alloc(String::from("d"));
+
+  pool.restore(checkpoint);
+  assert_eq!(pool.to_vec(), vec![(0, "a".into()), (1, "b".into()), (2, "c".into())]);
+  assert!(pool.validate().is_ok());
+  // An unused checkpoint drops its copy cleanly as well
+  drop(pool.checkpoint());
+}
+
+#[test]
+fn checkpoint_into_other_pond() {
+  let mut pool: Pond<_> = (0..4).collect();
+  pool.free(1);
+  let checkpoint = pool.checkpoint();
+
+  let mut other = Pond::new();
+  let stale = other.alloc_handle(9);
+  other.restore(checkpoint);
+  assert_eq!(other, pool);
+  assert!(other.validate().is_ok());
+  // Neither pond's handles carry over, even where the generations happened to line up
+  assert_eq!(other.get_handle(stale), None);
+  assert_eq!(other.get_handle(pool.handle(0).unwrap()), None);
+  assert_eq!(other.free(0), Some(0));
+  assert_eq!(other.alloc(5), 0);
+}
+
+#[test]
+fn vec_conversions() {
+  let mut pool = Pond::from(vec![1, 2, 3, 4]);
+  assert_eq!(pool[2], 3);
+  assert_eq!(pool.next_allocated(), 4);
+
+  // Holes are squeezed out on the way back
+  pool.free(1);
+  assert_eq!(pool.into_vec(), vec![1, 3, 4]);
+}
+
+#[test]
+fn slots() {
+  let slots = vec![Some(1), None, None, Some(4), None];
+  let pool = Pond::from_slots(slots.clone());
+  assert_eq!(pool.len(), 5);
+  assert_eq!(pool.next_allocated(), 1);
+  assert_eq!(pool[3], 4);
+  assert!(pool.validate().is_ok());
+  // Round trips without disturbing any index
+  assert_eq!(pool.into_slots(), slots);
+}
+
+#[test]
+fn entry() {
+  let mut pool = Pond::new();
+  pool.resize(3);
+  pool.write(1, 10);
+  // Occupied slots keep their data
+  assert_eq!(*pool.entry(1).unwrap().or_insert(0), 10);
+  // Vacant slots get filled
+  assert_eq!(*pool.entry(2).unwrap().or_insert(5), 5);
+  assert_eq!(pool.get(2), Some(&5));
+  pool.entry(1).unwrap().and_modify(|data| *data += 1).or_insert(0);
+  pool.entry(0).unwrap().and_modify(|data| *data += 1).or_insert(7);
+  assert_eq!(pool.safe_data(), vec![Some(&7), Some(&11), Some(&5)]);
+  assert!(pool.entry(3).is_none());
+  assert!(pool.validate().is_ok());
+}
+
+#[test]
+fn get_cloned() {
+  let mut pool = Pond::new();
+  let idx = pool.alloc(String::from("lily"));
+  let mut copy = pool.get_cloned(idx).unwrap();
+  copy.push('s');
+  pool.write(idx, copy);
+  assert_eq!(pool[idx], "lilys");
+  assert_eq!(pool.get_cloned(idx + 1), None);
+}
+
+#[test]
+fn drop_frees_data() {
+  let shared = std::rc::Rc::new(());
+  let mut pool = Pond::new();
+  pool.set_leak_warning(true);
+  for _ in 0 .. 4 { let _ = pool.alloc(shared.clone()); }
+  pool.free(1);
+  assert_eq!(std::rc::Rc::strong_count(&shared), 4);
+  drop(pool);
+  assert_eq!(std::rc::Rc::strong_count(&shared), 1);
+}
+
+#[test]
+fn upgrade() {
+  let mut pool = Pond::new();
+  let idx = pool.alloc(1);
+  let weak = pool.handle(idx).unwrap();
+  assert_eq!(pool.upgrade(weak), Some(idx));
+  pool.free(idx);
+  assert_eq!(pool.upgrade(weak), None);
+  // The slot gets reused, the old handle must not match the new occupant
+  assert_eq!(pool.alloc(2), idx);
+  assert_eq!(pool.upgrade(weak), None);
+  assert_eq!(pool.upgrade(pool.handle(idx).unwrap()), Some(idx));
+}
+
+#[test]
+fn get_pair_mut() {
+  let mut pool: Pond<_> = (0..3).collect();
+  let (a, b) = pool.get_pair_mut(0, 2).unwrap();
+  std::mem::swap(a, b);
+  assert_eq!(pool.safe_data(), vec![Some(&2), Some(&1), Some(&0)]);
+  assert!(pool.get_pair_mut(1, 1).is_none());
+  assert!(pool.get_pair_mut(1, 3).is_none());
+  pool.free(0);
+  assert!(pool.get_pair_mut(0, 1).is_none());
+}
+
 #[test]
 fn memory_reuse() {
   let mut pool = Pond::new();
@@ -72,6 +657,120 @@ fn memory_reuse() {
   assert_eq!(*pool.get(idx3).unwrap(), 3);
 }
 
+#[test]
+fn handles() {
+  let mut pool = Pond::new();
+  let handle = pool.alloc_handle(1);
+  assert_eq!(*pool.get_handle(handle).unwrap(), 1);
+  *pool.get_handle_mut(handle).unwrap() = 2;
+  assert_eq!(pool.handle(handle.index()), Some(handle));
+
+  // Free the slot and let someone else reuse it
+  assert_eq!(pool.free_handle(handle), Some(2));
+  let reused = pool.alloc_handle(3);
+  assert_eq!(reused.index(), handle.index());
+
+  // The old handle must not see the new occupant
+  assert_eq!(pool.get_handle(handle), None);
+  assert_eq!(pool.get_handle_mut(handle), None);
+  assert_eq!(pool.free_handle(handle), None);
+  assert_eq!(*pool.get_handle(reused).unwrap(), 3);
+
+  // Overwriting also counts as the occupant leaving
+  pool.write(reused.index(), 4);
+  assert_eq!(pool.get_handle(reused), None);
+}
+
+#[test]
+fn handles_defrag() {
+  let mut pool = Pond::new();
+  let front = pool.alloc_handle(0);
+  let back = pool.alloc_handle(1);
+  pool.free_handle(front).unwrap();
+
+  let remap = pool.defrag();
+  let new_idx = remap[&back.index()];
+  // Moved data invalidates the handle, but a fresh one can be taken from the remapped index
+  assert_eq!(pool.get_handle(back), None);
+  let moved = pool.handle(new_idx).unwrap();
+  assert_eq!(*pool.get_handle(moved).unwrap(), 1);
+  // Stale handles to trimmed slots stay stale after the pond regrows
+  _ = pool.trim();
+  pool.write(back.index(), 5);
+  assert_eq!(pool.get_handle(back), None);
+}
+
+#[test]
+fn swap() {
+  let mut pool: Pond<_> = (0..3).collect();
+  pool.free(2);
+
+  // Two occupied slots trade places
+  pool.swap(0, 1).unwrap();
+  assert_eq!(*pool.get(0).unwrap(), 1);
+  assert_eq!(*pool.get(1).unwrap(), 0);
+
+  // Swapping with a free slot moves the data
+  pool.swap(0, 2).unwrap();
+  assert_eq!(pool.get(0), None);
+  assert_eq!(*pool.get(2).unwrap(), 1);
+  assert_eq!(pool.next_allocated(), 0);
+
+  // Out of bounds is rejected without touching anything
+  assert_eq!(pool.swap(2, 3), None);
+  assert_eq!(*pool.get(2).unwrap(), 1);
+}
+
+#[test]
+fn move_to() {
+  let mut pool: Pond<_> = (0..4).collect();
+  pool.free(1);
+  pool.move_to(3, 1).unwrap();
+  assert_eq!(*pool.get(1).unwrap(), 3);
+  assert!(pool.is_free(3));
+
+  // A free source, an occupied destination and an out of bounds destination are all rejected
+  assert_eq!(pool.move_to(3, 0), None);
+  assert_eq!(pool.move_to(0, 2), None);
+  assert_eq!(pool.move_to(0, 4), None);
+  assert_eq!(*pool.get(0).unwrap(), 0);
+  assert_eq!(*pool.get(2).unwrap(), 2);
+}
+
+#[test]
+fn merge() {
+  let mut pool: Pond<_> = ["a", "b", "c"].into_iter().collect();
+  pool.free(1);
+  let mut other: Pond<_> = ["x", "y", "z"].into_iter().collect();
+  other.free(0);
+
+  let remap = pool.merge(other);
+  assert_eq!(remap.len(), 2);
+  // The hole is filled first, then the rest is appended
+  assert_eq!(remap[&1], 1);
+  assert_eq!(remap[&2], 3);
+  assert_eq!(pool[remap[&1]], "y");
+  assert_eq!(pool[remap[&2]], "z");
+  // Existing data stays put
+  assert_eq!(pool[0], "a");
+  assert_eq!(pool[2], "c");
+}
+
+#[test]
+fn split_off() {
+  let mut pool: Pond<_> = (0..6).collect();
+  pool.free(2);
+  let (evens, remap) = pool.split_off(|_, val| val % 2 == 0);
+
+  assert_eq!(remap.len(), 2);
+  assert_eq!(evens[remap[&0]], 0);
+  assert_eq!(evens[remap[&4]], 4);
+  assert_eq!(evens.len(), 2);
+  // The split slots are gone from the original, the rest stay put
+  assert!(pool.is_free(0) && pool.is_free(4));
+  assert_eq!(pool.to_vec(), vec![(1, 1), (3, 3), (5, 5)]);
+}
+
 #[test]
 fn defrag() {
   let mut pool = Pond::new();
@@ -92,6 +791,115 @@ fn defrag() {
 }
 
 #[test]
+fn defrag_patterns() {
+  // (length, occupied slots): all free, only the first or last slot, and alternating patterns
+  let patterns: [(usize, &[usize]); 6] = [
+    (8, &[]),
+    (8, &[0]),
+    (8, &[7]),
+    (8, &[0, 2, 4, 6]),
+    (8, &[1, 3, 5, 7]),
+    (1, &[0]),
+  ];
+  for (len, occupied) in patterns {
+    let mut pool = Pond::new();
+    pool.resize(len);
+    for idx in occupied { pool.write(*idx, *idx); }
+
+    let remap = pool.defrag();
+    assert!(pool.validate().is_ok());
+    // Everything is packed at the front and nothing was lost
+    assert_eq!(pool.next_allocated(), occupied.len());
+    let mut values: Vec<_> = (0..occupied.len()).map(|idx| *pool.get(idx).unwrap()).collect();
+    values.sort();
+    assert_eq!(values, occupied);
+    for (old, new) in remap { assert_eq!(*pool.get(new).unwrap(), old) }
+  }
+}
+
+#[test]
+fn defrag_with() {
+  let mut pool: Pond<_> = (0..10).collect();
+  for idx in [1, 2, 6] { pool.free(idx); }
+  let full_remap = pool.clone().defrag();
+
+  let mut moves = Vec::new();
+  pool.defrag_with(|old, new| moves.push((old, new)));
+  // Every move is reported once, matching the hashmap version
+  assert_eq!(moves, vec![(9, 1), (8, 2), (7, 6)]);
+  assert_eq!(moves.into_iter().collect::<std::collections::HashMap<_, _>>(), full_remap);
+}
+
+#[test]
+fn defrag_into() {
+  let mut pool: Pond<_> = (0..10).collect();
+  for idx in [1, 2, 6] { pool.free(idx); }
+  let mut moves = vec![(100, 100)];
+  pool.defrag_into(&mut moves);
+  // Existing contents are kept and the moves are appended in order
+  assert_eq!(moves, vec![(100, 100), (9, 1), (8, 2), (7, 6)]);
+}
+
+#[test]
+fn defrag_step() {
+  let mut pool: Pond<_> = (0..20).collect();
+  for idx in [0, 3, 4, 7, 10, 11, 15] { pool.free(idx); }
+  let mut expected = pool.clone();
+  let full_remap = expected.defrag();
+
+  // Small budgets report progress until the pond is compact
+  let mut remap = std::collections::HashMap::new();
+  loop {
+    let (partial, done) = pool.defrag_step(2);
+    assert!(partial.len() <= 2);
+    remap.extend(partial);
+    if done { break }
+  }
+  assert_eq!(remap, full_remap);
+  assert_eq!(pool.safe_data(), expected.safe_data());
+
+  // A compact pond is done without moving anything
+  assert_eq!(pool.defrag_step(1), (std::collections::HashMap::new(), true));
+}
+
+#[test]
+fn defrag_stable() {
+  let mut pool: Pond<_> = (0..10).collect();
+  for idx in [1, 4, 5, 8] { pool.free(idx); }
+
+  let remapped = pool.defrag_stable();
+  assert_eq!(remapped.len(), 5);
+  assert_eq!(remapped[&9], 5);
+
+  // Survivors are packed at the front in their original order
+  let survivors: Vec<_> = (0..6).map(|idx| *pool.get(idx).unwrap()).collect();
+  assert_eq!(survivors, vec![0, 2, 3, 6, 7, 9]);
+  assert_eq!(pool.next_allocated(), 6);
+}
+
+#[test]
+fn apply_remap_indices() {
+  let mut pool: Pond<_> = (0..5).collect();
+  pool.free(0);
+  pool.free(2);
+  let mut indices = vec![1, 3, 4];
+  let mut single = Some(4);
+  let mut none: Option<usize> = None;
+
+  let remap = pool.defrag();
+  apply_remap(&mut indices, &remap);
+  apply_remap(&mut single, &remap);
+  apply_remap(&mut none, &remap);
+
+  // 1 didn't move, 4 and 3 filled the holes
+  assert_eq!(indices, vec![1, 2, 0]);
+  assert_eq!(single, Some(0));
+  assert_eq!(none, None);
+  for (idx, val) in indices.iter().zip([1, 3, 4]) { assert_eq!(*pool.get(*idx).unwrap(), val) }
+}
+
+#[test]
+#[allow(clippy::redundant_pattern_matching)]
 fn trim_normal() {
   let mut pool = Pond::new();
   let mut indices: Vec<_> = (0..5).map(|i| pool.alloc(i)).collect();
@@ -162,6 +970,82 @@ fn trim_free() {
   assert_eq!(pool.next_allocated(), 0);
 }
 
+#[test]
+fn shrink_to_fit() {
+  let mut pool: Pond<_> = (0..5).collect();
+  pool.free(1);
+  pool.free(3);
+  pool.free(4);
+  pool.shrink_to_fit();
+
+  // The tail is dropped but the interior hole is left alone
+  assert_eq!(pool.len(), 3);
+  assert!(pool.unsafe_data().capacity() >= 3);
+  assert_eq!(*pool.get(2).unwrap(), 2);
+  assert_eq!(pool.next_allocated(), 1);
+
+  // An occupied tail is a no-op
+  pool.shrink_to_fit();
+  assert_eq!(pool.len(), 3);
+}
+
+#[test]
+fn free_indices() {
+  let mut pool: Pond<_> = (0..10).collect();
+  assert!(pool.free_indices().is_empty());
+  for idx in [7, 2, 9, 3] { pool.free(idx); }
+  assert_eq!(pool.free_indices(), vec![2, 3, 7, 9]);
+}
+
+#[test]
+fn fragmentation() {
+  let mut pool = Pond::new();
+  assert_eq!(pool.fragmentation(), 0.0);
+
+  pool.extend(0..8);
+  for idx in [1, 2, 5] { pool.free(idx); }
+  assert_eq!(pool.occupied_count(), 5);
+  assert_eq!(pool.free_count(), 3);
+  assert_eq!(pool.fragmentation(), 3.0 / 8.0);
+
+  _ = pool.trim();
+  assert_eq!(pool.fragmentation(), 0.0);
+}
+
+#[test]
+fn shrink_to_fit_capacity() {
+  let mut pool = Pond::new();
+  pool.resize(1000);
+  pool.extend(0..4);
+  pool.shrink_to_fit();
+  // The backing memory is released down to what's actually used
+  assert_eq!(pool.len(), 4);
+  assert!(pool.stats().capacity < 1000);
+  assert_eq!(*pool.get(3).unwrap(), 3);
+}
+
+#[test]
+fn stats() {
+  let mut pool = Pond::<u64>::new();
+  assert_eq!(pool.stats().fragmentation_ratio(), 0.0);
+
+  pool.extend(0..6);
+  pool.free(1);
+  pool.free(2);
+  pool.free(5);
+
+  let stats = pool.stats();
+  assert_eq!(stats.occupied, 3);
+  assert_eq!(stats.free, 3);
+  assert_eq!(stats.len, 6);
+  // Slot 5 trails the data, so only 1 and 2 count as interior
+  assert_eq!(stats.interior_free, 2);
+  assert_eq!(stats.capacity, pool.unsafe_data().capacity());
+  assert_eq!(stats.bytes_for_data, stats.capacity * 8);
+  assert_eq!(stats.fragmentation, 0.5);
+  assert_eq!(stats.fragmentation_ratio(), 2.0 / stats.capacity as f64);
+}
+
 #[test]
 fn stress() {
   const N: u32 = 1_000_000;
@@ -0,0 +1,23 @@
+#![cfg(feature = "rayon")]
+use lilypads::Pond;
+use rayon::prelude::*;
+
+#[test]
+fn par_iter() {
+  let mut pond: Pond<u64> = (0..10_000).collect();
+  pond.free_many((0..10_000).step_by(3));
+  let serial: u64 = (0..pond.len()).filter_map(|idx| pond.get(idx)).sum();
+  assert_eq!(pond.par_iter().map(|(_, val)| *val).sum::<u64>(), serial);
+  // Free slots are skipped
+  assert_eq!(pond.par_iter().count(), pond.occupied_count());
+}
+
+#[test]
+fn par_iter_mut() {
+  let mut pond: Pond<u64> = (0..1000).collect();
+  pond.free(10);
+  pond.par_iter_mut().for_each(|(idx, val)| *val += idx as u64);
+  assert_eq!(pond[7], 14);
+  assert_eq!(pond.get(10), None);
+  assert!(pond.validate().is_ok());
+}
@@ -0,0 +1,30 @@
+#![cfg(feature = "slab")]
+use lilypads::Pond;
+use slab::Slab;
+
+#[test]
+fn from_slab() {
+  let mut slab = Slab::new();
+  let a = slab.insert("a");
+  let b = slab.insert("b");
+  let c = slab.insert("c");
+  slab.remove(b);
+
+  // Keys carry over as indices, vacant entries become free slots
+  let pond = Pond::from(slab);
+  assert_eq!(pond[a], "a");
+  assert_eq!(pond.get(b), None);
+  assert_eq!(pond[c], "c");
+}
+
+#[test]
+fn to_slab() {
+  let mut pond: Pond<_> = (0..4).collect();
+  pond.free(1);
+  let slab = pond.to_slab();
+  assert_eq!(slab.len(), 3);
+  assert_eq!(slab.get(1), None);
+  assert_eq!(slab[3], 3);
+  // Round trip back into a pond
+  assert_eq!(Pond::from(slab), pond);
+}
@@ -0,0 +1,45 @@
+use lilypads::{Pond, SyncPond};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn shared_alloc() {
+  let pool = Arc::new(SyncPond::new());
+  let handles: Vec<_> = (0..4).map(|t| {
+    let pool = Arc::clone(&pool);
+    thread::spawn(move || (0..100).map(|i| pool.alloc(t * 100 + i)).collect::<Vec<_>>())
+  }).collect();
+
+  // Every thread's data should land somewhere unique and be readable from here
+  let mut seen = Vec::new();
+  for (t, handle) in handles.into_iter().enumerate() {
+    for (i, idx) in handle.join().unwrap().into_iter().enumerate() {
+      assert_eq!(*pool.get(idx).unwrap(), t * 100 + i);
+      seen.push(idx);
+    }
+  }
+  seen.sort();
+  seen.dedup();
+  assert_eq!(seen.len(), 400);
+}
+
+#[test]
+fn get_and_free() {
+  let pool = SyncPond::from(Pond::new());
+  let idx = pool.alloc(42);
+  assert_eq!(*pool.get(idx).unwrap(), 42);
+  assert_eq!(pool.free(idx), Some(42));
+  assert!(pool.get(idx).is_none());
+
+  // Bigger edits go through the write guard
+  pool.lock_write().write(3, 7);
+  assert_eq!(pool.lock_read().get(3), Some(&7));
+  assert_eq!(*pool.into_inner().get(3).unwrap(), 7);
+}
+
+#[test]
+fn auto_traits() {
+  fn assert_send_sync<T: Send + Sync>() {}
+  assert_send_sync::<Pond<u32>>();
+  assert_send_sync::<SyncPond<u32>>();
+}